@@ -22,6 +22,7 @@ mod tests {
             retry_attempts: 3,
             retry_delay: 5,
             heartbeat_interval: 60,
+            ..Default::default()
         }
     }
 
@@ -40,6 +41,7 @@ mod tests {
             retry_attempts: 3,
             retry_delay: 5,
             heartbeat_interval: 60,
+            ..Default::default()
         }
     }
 
@@ -476,5 +478,121 @@ mod tests {
             assert!(config.validate().is_ok(), "URL {} should be valid", url);
         }
     }
+
+    #[test]
+    fn test_config_default_timestamp_format() {
+        let config = Config::default();
+        assert_eq!(config.timestamp_format, TimestampFormat::Rfc3339);
+    }
+
+    #[test]
+    fn test_config_validation_pkcs12_identity() {
+        let mut config = create_minimal_config();
+        config.tls_client_identity_p12 = Some("/path/to/client.p12".to_string());
+        config.tls_client_identity_p12_password = Some("secret".to_string());
+        assert!(config.validate().is_ok());
+
+        // PKCS#12 identity cannot be combined with separate PEM cert/key
+        config.tls_client_cert = Some("/path/to/client.crt".to_string());
+        config.tls_client_key = Some("/path/to/client.key".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_config_validation_keepalive_disabled() {
+        let mut config = create_valid_config();
+        config.tcp_keepalive_secs = None;
+        config.pool_idle_timeout_secs = Some(0);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_from_dir_merges_fragments() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let base = r#"
+        {
+            "server_url": "https://api.insec.com",
+            "agent_id": "test-agent-123",
+            "tenant_id": "test-tenant",
+            "log_level": "info"
+        }
+        "#;
+        let override_fragment = r#"
+        {
+            "log_level": "debug"
+        }
+        "#;
+
+        fs::write(temp_dir.path().join("10-base.json"), base).unwrap();
+        fs::write(temp_dir.path().join("20-logging.json"), override_fragment).unwrap();
+        fs::write(temp_dir.path().join("README.txt"), "not a config fragment").unwrap();
+
+        let config = Config::from_dir(temp_dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(config.server_url, "https://api.insec.com");
+        assert_eq!(config.agent_id, "test-agent-123");
+        assert_eq!(config.log_level, "debug");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_config_strict_mode_rejects_unknown_keys() {
+        let config_data = r#"
+        {
+            "server_url": "https://api.insec.com",
+            "agent_id": "test-agent-123",
+            "tenant_id": "test-tenant",
+            "collecton_interval": 30
+        }
+        "#;
+
+        let result = Config::from_str_with_mode(config_data, ParseMode::Strict);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("collecton_interval"));
+
+        let result = Config::from_str_with_mode(config_data, ParseMode::Lenient);
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_config_error_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("broken_config.json");
+        fs::write(&config_path, "{ not json").unwrap();
+
+        // Fail-closed is the default and refuses to start
+        let result = Config::load(config_path.to_str().unwrap(), ConfigErrorPolicy::default());
+        assert!(result.is_err());
+
+        // Fail-open falls back to built-in defaults
+        let mut config = Config::load(config_path.to_str().unwrap(), ConfigErrorPolicy::FailOpen).unwrap();
+        assert_eq!(config.collection_interval, Config::default().collection_interval);
+        assert_eq!(ConfigErrorPolicy::default(), ConfigErrorPolicy::FailClosed);
+
+        // ...and still yields a running collector; only the endpoint is redirected to the mock
+        config.server_url = mockito::server_url();
+        let _register_mock = mockito::mock("POST", "/api/v1/agents/register")
+            .with_status(201)
+            .with_body(r#"{"status": "registered"}"#)
+            .expect(1)
+            .create();
+
+        let collector = TelemetryCollector::new(config);
+        collector.start_up().await.unwrap();
+
+        let defaults = Config::default();
+        assert_eq!(collector.config.collection_interval, defaults.collection_interval);
+        assert_eq!(collector.config.max_batch_size, defaults.max_batch_size);
+        assert_eq!(collector.config.heartbeat_interval, defaults.heartbeat_interval);
+        _register_mock.assert();
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_config_validation_named_pipe_transport_non_windows() {
+        let mut config = create_valid_config();
+        config.transport = TransportKind::NamedPipe(r"\\.\pipe\insec".to_string());
+        assert!(config.validate().is_err());
+    }
 }</content>
 <parameter name="filePath">/workspaces/insec/tests/unit/agent/config_test.rs
@@ -40,6 +40,243 @@ mod tests {
         }
     }
 
+    struct MockTransport {
+        succeed: bool,
+        batches: Arc<Mutex<usize>>,
+    }
+
+    impl MockTransport {
+        fn succeeding() -> Self {
+            Self { succeed: true, batches: Arc::new(Mutex::new(0)) }
+        }
+
+        fn failing() -> Self {
+            Self { succeed: false, batches: Arc::new(Mutex::new(0)) }
+        }
+
+        async fn received_batches(&self) -> usize {
+            *self.batches.lock().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Transport for MockTransport {
+        async fn send(&self, _events: &[TelemetryEvent]) -> Result<(), ClientError> {
+            *self.batches.lock().await += 1;
+            if self.succeed {
+                Ok(())
+            } else {
+                Err(ClientError::Transport("mock failure".to_string()))
+            }
+        }
+    }
+
+    struct MockCommandDispatcher {
+        flushes: Arc<Mutex<usize>>,
+    }
+
+    impl MockCommandDispatcher {
+        fn new() -> Self {
+            Self { flushes: Arc::new(Mutex::new(0)) }
+        }
+
+        async fn flush_calls(&self) -> usize {
+            *self.flushes.lock().await
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl CommandDispatcher for MockCommandDispatcher {
+        async fn flush(&self) {
+            *self.flushes.lock().await += 1;
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockClock {
+        now: Arc<std::sync::Mutex<chrono::DateTime<Utc>>>,
+    }
+
+    impl MockClock {
+        fn new(start: chrono::DateTime<Utc>) -> Self {
+            Self {
+                now: Arc::new(std::sync::Mutex::new(start)),
+            }
+        }
+
+        fn advance(&self, by: chrono::Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now = *now + by;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> chrono::DateTime<Utc> {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    // Certificate material for the local TLS mock, generated per test
+    struct TestCertificate {
+        cert_pem: String,
+        key_pem: String,
+    }
+
+    impl TestCertificate {
+        fn generate(subject_alt_names: &[&str]) -> Self {
+            let names = subject_alt_names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+            let cert = rcgen::generate_simple_self_signed(names).unwrap();
+            Self {
+                cert_pem: cert.serialize_pem().unwrap(),
+                key_pem: cert.serialize_private_key_pem(),
+            }
+        }
+
+        fn from_pem(cert_pem: &str, key_pem: &str) -> Self {
+            Self {
+                cert_pem: cert_pem.to_string(),
+                key_pem: key_pem.to_string(),
+            }
+        }
+
+        fn x509(&self) -> openssl::x509::X509 {
+            openssl::x509::X509::from_pem(self.cert_pem.as_bytes()).unwrap()
+        }
+
+        fn private_key(&self) -> openssl::pkey::PKey<openssl::pkey::Private> {
+            openssl::pkey::PKey::private_key_from_pem(self.key_pem.as_bytes()).unwrap()
+        }
+
+        fn der(&self) -> Vec<u8> {
+            self.x509().to_der().unwrap()
+        }
+
+        fn write_pem(&self, dir: &std::path::Path, name: &str) -> (String, String) {
+            let cert_path = dir.join(format!("{}.crt", name));
+            let key_path = dir.join(format!("{}.key", name));
+            std::fs::write(&cert_path, &self.cert_pem).unwrap();
+            std::fs::write(&key_path, &self.key_pem).unwrap();
+            (cert_path.display().to_string(), key_path.display().to_string())
+        }
+
+        fn write_p12(&self, dir: &std::path::Path, name: &str, password: &str) -> String {
+            let p12 = openssl::pkcs12::Pkcs12::builder()
+                .name(name)
+                .pkey(&self.private_key())
+                .cert(&self.x509())
+                .build2(password)
+                .unwrap();
+            let p12_path = dir.join(format!("{}.p12", name));
+            std::fs::write(&p12_path, p12.to_der().unwrap()).unwrap();
+            p12_path.display().to_string()
+        }
+    }
+
+    // HTTPS endpoint for TLS-level behaviour that mockito (plain HTTP) cannot serve
+    struct MockTlsServer {
+        port: u16,
+        client_certs: Arc<Mutex<Vec<Vec<u8>>>>,
+        handle: tokio::task::JoinHandle<()>,
+    }
+
+    impl MockTlsServer {
+        // An empty `client_cas` disables client authentication
+        async fn start(identity: &TestCertificate, client_cas: &[&TestCertificate]) -> Self {
+            use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+
+            let mut builder = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls_server()).unwrap();
+            builder.set_security_level(0); // Allow deliberately weak fixtures to be served
+            builder.set_certificate(&identity.x509()).unwrap();
+            builder.set_private_key(&identity.private_key()).unwrap();
+            if !client_cas.is_empty() {
+                let mut store = openssl::x509::store::X509StoreBuilder::new().unwrap();
+                for ca in client_cas {
+                    store.add_cert(ca.x509()).unwrap();
+                }
+                builder.set_verify_cert_store(store.build()).unwrap();
+                builder.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+            }
+            let acceptor = builder.build();
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let client_certs = Arc::new(Mutex::new(Vec::new()));
+            let handle = tokio::spawn({
+                let client_certs = Arc::clone(&client_certs);
+                async move {
+                    while let Ok((stream, _)) = listener.accept().await {
+                        let ssl = openssl::ssl::Ssl::new(acceptor.context()).unwrap();
+                        let mut tls = tokio_openssl::SslStream::new(ssl, stream).unwrap();
+                        if std::pin::Pin::new(&mut tls).accept().await.is_err() {
+                            continue;
+                        }
+                        if let Some(cert) = tls.ssl().peer_certificate() {
+                            client_certs.lock().await.push(cert.to_der().unwrap());
+                        }
+                        respond_success(&mut tls).await;
+                    }
+                }
+            });
+
+            Self { port, client_certs, handle }
+        }
+
+        fn url(&self) -> String {
+            format!("https://localhost:{}", self.port)
+        }
+
+        fn ip_url(&self) -> String {
+            format!("https://127.0.0.1:{}", self.port)
+        }
+
+        async fn presented_client_certs(&self) -> Vec<Vec<u8>> {
+            self.client_certs.lock().await.clone()
+        }
+    }
+
+    impl Drop for MockTlsServer {
+        fn drop(&mut self) {
+            self.handle.abort();
+        }
+    }
+
+    // Reads one HTTP/1.1 request and answers with a canned success body
+    async fn respond_success<S>(stream: &mut S)
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt};
+
+        let mut reader = tokio::io::BufReader::new(stream);
+        let mut content_length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            if line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+        let mut request_body = vec![0u8; content_length];
+        if reader.read_exact(&mut request_body).await.is_err() {
+            return;
+        }
+
+        let body = r#"{"status": "success"}"#;
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let stream = reader.into_inner();
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+    }
+
     // Test data factories
     fn create_test_config() -> Config {
         Config {
@@ -56,6 +293,7 @@ mod tests {
             retry_attempts: 3,
             retry_delay: 1,
             heartbeat_interval: 60,
+            ..Default::default()
         }
     }
 
@@ -80,6 +318,19 @@ mod tests {
             .collect()
     }
 
+    fn create_test_heartbeat() -> HeartbeatData {
+        HeartbeatData {
+            agent_id: "test-agent-123".to_string(),
+            timestamp: Utc::now(),
+            status: "healthy".to_string(),
+            version: "1.0.0".to_string(),
+            uptime_seconds: 3600,
+            memory_usage_mb: 50.5,
+            cpu_usage_percent: 15.2,
+            ..Default::default()
+        }
+    }
+
     #[tokio::test]
     async fn test_http_client_initialization() {
         let config = create_test_config();
@@ -271,15 +522,7 @@ mod tests {
         let config = create_test_config();
         let client = HttpClient::new(config);
 
-        let heartbeat = HeartbeatData {
-            agent_id: "test-agent-123".to_string(),
-            timestamp: Utc::now(),
-            status: "healthy".to_string(),
-            version: "1.0.0".to_string(),
-            uptime_seconds: 3600,
-            memory_usage_mb: 50.5,
-            cpu_usage_percent: 15.2,
-        };
+        let heartbeat = create_test_heartbeat();
 
         // Mock heartbeat endpoint
         let _mock = mock("POST", "/api/v1/heartbeat")
@@ -516,6 +759,1104 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_shadow_send_mirrors_batch() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.shadow_server_url = Some(format!("{}/shadow", mockito::server_url()));
+        let client = HttpClient::new(config);
+        let events = create_test_events(2);
+
+        // Primary succeeds, shadow fails; only the primary decides the outcome
+        let _primary = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect(1)
+            .create();
+        let _shadow = mock("POST", "/shadow/api/v1/events")
+            .with_status(500)
+            .with_body(r#"{"error": "Internal server error"}"#)
+            .expect(1)
+            .create();
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+
+        _primary.assert();
+        _shadow.assert();
+        let metrics = client.get_request_metrics().await;
+        assert_eq!(metrics.shadow_failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_unauthorized_refreshes_and_retries() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let _unauthorized = mock("POST", "/api/v1/events")
+            .match_header("Authorization", "Bearer expired-token")
+            .with_status(401)
+            .with_body(r#"{"error": "Token expired"}"#)
+            .expect(1)
+            .create();
+        let _token_mock = mock("POST", "/api/v1/auth/refresh")
+            .with_status(200)
+            .with_body(r#"{"token": "new-refreshed-token", "expires_in": 3600}"#)
+            .expect(1)
+            .create();
+        let _retry = mock("POST", "/api/v1/events")
+            .match_header("Authorization", "Bearer new-refreshed-token")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect(1)
+            .create();
+
+        let result = client.send_event_batch_with_retry(&events, "expired-token").await;
+        assert!(result.is_ok());
+        _token_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_fails_without_refresh() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let _forbidden = mock("POST", "/api/v1/events")
+            .with_status(403)
+            .with_body(r#"{"error": "Forbidden"}"#)
+            .expect(1) // No retries on permission errors
+            .create();
+        let _token_mock = mock("POST", "/api/v1/auth/refresh")
+            .expect(0)
+            .create();
+
+        let result = client.send_event_batch_with_retry(&events, "test-token").await;
+        assert!(matches!(result, Err(ClientError::Forbidden(_))));
+        _forbidden.assert();
+        _token_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_failure_window() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.circuit_failure_threshold = 3;
+        config.circuit_failure_window_secs = 10;
+        let clock = MockClock::new(Utc::now());
+        let client = HttpClient::new(config).with_clock(clock.clone());
+        let events = create_test_events(1);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(503)
+            .with_body(r#"{"error": "Service unavailable"}"#)
+            .expect(6)
+            .create();
+
+        // Isolated failures spread beyond the window
+        for _ in 0..3 {
+            let _ = client.send_event_batch(&events, "test-token").await;
+            clock.advance(chrono::Duration::seconds(11));
+        }
+        assert_eq!(client.circuit_state().await, CircuitState::Closed);
+
+        // A burst within the window
+        for _ in 0..3 {
+            let _ = client.send_event_batch(&events, "test-token").await;
+            clock.advance(chrono::Duration::seconds(1));
+        }
+        assert_eq!(client.circuit_state().await, CircuitState::Open);
+        _mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_pkcs12_client_identity() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let server_identity = TestCertificate::generate(&["localhost"]);
+        let client_identity = TestCertificate::generate(&["test-agent-123"]);
+
+        // Server refuses the handshake unless this client identity is presented
+        let server = MockTlsServer::start(&server_identity, &[&client_identity]).await;
+
+        let mut config = create_test_config();
+        config.server_url = server.url();
+        config.tls_ca_cert = Some(server_identity.write_pem(temp_dir.path(), "ca").0);
+        config.tls_client_identity_p12 = Some(client_identity.write_p12(temp_dir.path(), "client", "insec-test"));
+        config.tls_client_identity_p12_password = Some("insec-test".to_string());
+        let client = HttpClient::new(config);
+
+        let events = create_test_events(1);
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+        assert_eq!(server.presented_client_certs().await, vec![client_identity.der()]);
+    }
+
+    #[tokio::test]
+    async fn test_field_map_renames_serialized_keys() {
+        let mut config = create_test_config();
+        config.field_map.insert("process_name".to_string(), "proc.name".to_string());
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let body = client.serialize_batch(&events).unwrap();
+
+        let data = &body["events"][0]["data"];
+        assert_eq!(data["proc.name"], "test0.exe");
+        assert!(data.get("process_name").is_none());
+        assert!(data.get("pid").is_some()); // Unmapped keys pass through
+
+        // The stored event is untouched
+        assert!(events[0].data.contains_key("process_name"));
+        assert!(!events[0].data.contains_key("proc.name"));
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_transport_policies() {
+        let events = create_test_events(2);
+
+        let healthy = Arc::new(MockTransport::succeeding());
+        let failing = Arc::new(MockTransport::failing());
+
+        let all = FanOutTransport::new(
+            vec![healthy.clone(), failing.clone()],
+            FanOutPolicy::AllMustSucceed,
+        );
+        assert!(all.send(&events).await.is_err());
+
+        let any = FanOutTransport::new(
+            vec![healthy.clone(), failing.clone()],
+            FanOutPolicy::AnySucceeds,
+        );
+        assert!(any.send(&events).await.is_ok());
+
+        // Both transports received the batch under each policy
+        assert_eq!(healthy.received_batches().await, 2);
+        assert_eq!(failing.received_batches().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_command_long_poll_dispatches_once() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+        let dispatcher = Arc::new(MockCommandDispatcher::new());
+
+        // The same command id is returned twice; it must only run once
+        let _mock = mock("GET", "/api/v1/agents/commands")
+            .match_header("X-Agent-ID", "test-agent-123")
+            .with_status(200)
+            .with_body(r#"{"commands": [{"id": "cmd-1", "type": "flush"}]}"#)
+            .expect_at_least(3)
+            .create();
+
+        let poller = CommandPoller::new(client, dispatcher.clone());
+        let handle = tokio::spawn(async move { poller.run("test-token").await });
+
+        // A third poll means the duplicate from the second has already been handled
+        tokio::time::timeout(tokio::time::Duration::from_secs(5), async {
+            while !_mock.matched() {
+                tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("poller stopped polling");
+        handle.abort();
+
+        _mock.assert();
+        assert_eq!(dispatcher.flush_calls().await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_tcp_keepalive_configuration() {
+        let mut config = create_test_config();
+        config.tcp_keepalive_secs = Some(30);
+        config.pool_idle_timeout_secs = Some(90);
+        let client = HttpClient::new(config);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        let events = create_test_events(1);
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_tls_server_name_override() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let server_identity = TestCertificate::generate(&["api.insec.com"]);
+        let server = MockTlsServer::start(&server_identity, &[]).await;
+        let ca_cert = server_identity.write_pem(temp_dir.path(), "ca").0;
+
+        let client_for = |server_name: Option<&str>| {
+            let mut config = create_test_config();
+            config.server_url = server.ip_url();
+            config.tls_ca_cert = Some(ca_cert.clone());
+            config.tls_server_name = server_name.map(str::to_string);
+            HttpClient::new(config)
+        };
+        let events = create_test_events(1);
+
+        // Certificate is issued for api.insec.com; connecting by IP validates that name
+        let result = client_for(Some("api.insec.com")).send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+
+        // Verification stays enabled: a name the certificate does not cover is refused
+        let result = client_for(Some("other.insec.com")).send_event_batch(&events, "test-token").await;
+        assert!(result.is_err());
+
+        let result = client_for(None).send_event_batch(&events, "test-token").await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_token_refresh_margin() {
+        let mut config = create_test_config();
+        config.token_refresh_margin_secs = 300;
+        let client = HttpClient::new(config);
+
+        let _token_mock = mock("POST", "/api/v1/auth/refresh")
+            .with_status(200)
+            .with_body(r#"{"token": "new-refreshed-token", "expires_in": 3600}"#)
+            .create();
+
+        client.set_token("test-token", 3600).await;
+
+        tokio::time::advance(std::time::Duration::from_secs(3299)).await;
+        assert!(!client.token_needs_refresh().await);
+
+        tokio::time::advance(std::time::Duration::from_secs(1)).await;
+        assert!(client.token_needs_refresh().await);
+
+        // A margin longer than the lifetime refreshes immediately
+        client.set_token("short-token", 60).await;
+        assert!(client.token_needs_refresh().await);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_piggybacks_on_event_batch() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.piggyback_heartbeat = true;
+        let client = HttpClient::new(config);
+
+        let heartbeat = create_test_heartbeat();
+
+        let _events_mock = mock("POST", "/api/v1/events")
+            .match_body(Matcher::PartialJsonString(r#"{"heartbeat": {"status": "healthy"}}"#.to_string()))
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect(1)
+            .create();
+        let _heartbeat_mock = mock("POST", "/api/v1/heartbeat")
+            .expect(0)
+            .create();
+
+        client.queue_heartbeat(heartbeat).await;
+        let events = create_test_events(2);
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+
+        _events_mock.assert();
+        _heartbeat_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_request_latency_percentiles() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+
+        // Every response is delayed by a known 50ms
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body_from_fn(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                std::io::Write::write_all(w, br#"{"status": "success"}"#)
+            })
+            .expect(20)
+            .create();
+
+        for _ in 0..20 {
+            let events = create_test_events(1);
+            let result = client.send_event_batch(&events, "test-token").await;
+            assert!(result.is_ok());
+        }
+
+        let metrics = client.get_request_metrics().await;
+        let latency = &metrics.latency;
+        assert_eq!(latency.count, 20);
+        assert!(latency.p50 >= std::time::Duration::from_millis(50));
+        assert!(latency.p50 < std::time::Duration::from_millis(250));
+        assert!(latency.p50 <= latency.p95);
+        assert!(latency.p95 <= latency.p99);
+
+        let prometheus = client.render_prometheus_metrics().await;
+        assert!(prometheus.contains("insec_request_duration_seconds"));
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_status_fails_immediately() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(400)
+            .with_body(r#"{"error": "Bad request"}"#)
+            .expect(1) // No retries
+            .create();
+
+        let result = client.send_event_batch_with_retry(&events, "test-token").await;
+        assert!(result.is_err());
+        _mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_retryable_status_is_retried() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(503)
+            .with_body(r#"{"error": "Service unavailable"}"#)
+            .expect(4) // Initial + 3 retries
+            .create();
+
+        let result = client.send_event_batch_with_retry(&events, "test-token").await;
+        assert!(result.is_err());
+        _mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_critical_event_delivery_receipt() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+        let event = create_test_events(1).remove(0);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .match_body(Matcher::PartialJsonString(r#"{"events": [{"id": "test-event-0"}]}"#.to_string()))
+            .with_status(200)
+            .with_body(r#"{"status": "success", "receipts": [{"event_id": "test-event-0", "server_id": "srv-42", "received_at": "2024-01-01T00:00:00Z"}]}"#)
+            .expect(1)
+            .create();
+
+        let receipt = client.send_critical(&event, "test-token").await.unwrap();
+        assert_eq!(receipt.event_id, "test-event-0");
+        assert_eq!(receipt.server_id, "srv-42");
+        _mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_custom_envelope_builder() {
+        struct SourceEnvelope;
+
+        // A custom builder replaces the default batch envelope around the events array
+        impl EnvelopeBuilder for SourceEnvelope {
+            fn wrap(&self, events: serde_json::Value) -> serde_json::Value {
+                serde_json::json!({ "source": "insec", "payload": events })
+            }
+        }
+
+        let config = create_test_config();
+        let client = HttpClient::new(config).with_envelope_builder(Box::new(SourceEnvelope));
+        let events = create_test_events(2);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .match_body(Matcher::PartialJsonString(r#"{"source": "insec"}"#.to_string()))
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        let body = client.serialize_batch(&events).unwrap();
+        assert_eq!(body["source"], "insec");
+        assert_eq!(body["payload"].as_array().unwrap().len(), 2);
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_batch_envelope_metadata() {
+        let config = create_test_config();
+        let client = HttpClient::new(config);
+        let events = create_test_events(3);
+
+        let first = client.prepare_request(&events, "test-token");
+        let retry = client.prepare_request(&events, "test-token");
+
+        let envelope = first.envelope();
+        assert_eq!(envelope["agent_id"], "test-agent-123");
+        assert_eq!(envelope["tenant_id"], "test-tenant");
+        assert!(envelope.get("schema_version").is_some());
+        assert_eq!(envelope["event_count"], 3);
+        assert_eq!(envelope["events"].as_array().unwrap().len(), 3);
+        assert_eq!(envelope["batch_id"], retry.envelope()["batch_id"]);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_bare_array_format() {
+        let mut config = create_test_config();
+        config.legacy_batch_format = true;
+        let client = HttpClient::new(config);
+        let events = create_test_events(3);
+
+        let body = client.serialize_batch(&events).unwrap();
+        assert_eq!(body.as_array().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rotation_timezone_day_boundary() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = create_test_config();
+        config.rotation_timezone = Some("America/New_York".to_string());
+        let transport = FileTransport::new(temp_dir.path(), &config).unwrap();
+
+        // 2024-03-10T03:30:00Z is still March 9th in New York
+        let mut events = create_test_events(1);
+        events[0].timestamp = "2024-03-10T03:30:00Z".parse().unwrap();
+        transport.send(&events).await.unwrap();
+
+        assert!(temp_dir.path().join("events-2024-03-09.ndjson").exists());
+        assert!(!temp_dir.path().join("events-2024-03-10.ndjson").exists());
+        assert_eq!(transport.index_name_for(&events[0]), "insec-events-2024.03.09");
+    }
+
+    #[tokio::test]
+    async fn test_tls_reload_swaps_client_identity() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let server_identity = TestCertificate::generate(&["localhost"]);
+        let original = TestCertificate::generate(&["test-agent-123"]);
+        let rotated = TestCertificate::generate(&["test-agent-123"]);
+        let server = MockTlsServer::start(&server_identity, &[&original, &rotated]).await;
+
+        let mut config = create_test_config();
+        config.server_url = server.url();
+        config.tls_ca_cert = Some(server_identity.write_pem(temp_dir.path(), "ca").0);
+        let (cert, key) = original.write_pem(temp_dir.path(), "client");
+        config.tls_client_cert = Some(cert);
+        config.tls_client_key = Some(key);
+        let client = HttpClient::new(config.clone());
+
+        let events = create_test_events(1);
+        assert!(client.send_event_batch(&events, "test-token").await.is_ok());
+
+        let (cert, key) = rotated.write_pem(temp_dir.path(), "client-rotated");
+        config.tls_client_cert = Some(cert);
+        config.tls_client_key = Some(key);
+        client.reload_tls(&config).await.unwrap();
+
+        assert!(client.send_event_batch(&events, "test-token").await.is_ok());
+
+        // The server saw the original identity first, then only the rotated one
+        assert_eq!(server.presented_client_certs().await, vec![original.der(), rotated.der()]);
+    }
+
+    #[tokio::test]
+    async fn test_signed_configuration_verification() {
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let mut config = create_test_config();
+        config.config_signing_public_key = Some(base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            signing_key.verifying_key().as_bytes(),
+        ));
+        let client = HttpClient::new(config);
+
+        let payload = r#"{"collection_interval": 45, "max_batch_size": 150, "enable_compression": true, "rules": []}"#;
+        let signature = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            signing_key.sign(payload.as_bytes()).to_bytes(),
+        );
+
+        let _valid = mock("GET", "/api/v1/agents/config")
+            .with_status(200)
+            .with_header("X-Config-Signature", &signature)
+            .with_body(payload)
+            .create();
+        let result = client.sync_configuration("test-token").await;
+        assert_eq!(result.unwrap().collection_interval, 45);
+
+        let _tampered = mock("GET", "/api/v1/agents/config")
+            .with_status(200)
+            .with_header("X-Config-Signature", &signature)
+            .with_body(payload.replace("45", "3600"))
+            .create();
+        let result = client.sync_configuration("test-token").await;
+        assert!(matches!(result, Err(ClientError::InvalidConfigSignature)));
+    }
+
+    #[tokio::test]
+    async fn test_http2_multiplexing() {
+        use hyper::service::{make_service_fn, service_fn};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // mockito only speaks HTTP/1.1, so serve h2 (prior knowledge) with hyper
+        let connections = Arc::new(AtomicUsize::new(0));
+        let h2_requests = Arc::new(AtomicUsize::new(0));
+        let make_service = make_service_fn({
+            let connections = Arc::clone(&connections);
+            let h2_requests = Arc::clone(&h2_requests);
+            move |_| {
+                connections.fetch_add(1, Ordering::SeqCst);
+                let h2_requests = Arc::clone(&h2_requests);
+                async move {
+                    Ok::<_, std::convert::Infallible>(service_fn(move |request: hyper::Request<hyper::Body>| {
+                        if request.version() == hyper::Version::HTTP_2 {
+                            h2_requests.fetch_add(1, Ordering::SeqCst);
+                        }
+                        async {
+                            // Keep the streams overlapping
+                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                            Ok::<_, std::convert::Infallible>(hyper::Response::new(hyper::Body::from(r#"{"status": "success"}"#)))
+                        }
+                    }))
+                }
+            }
+        });
+        let server = hyper::Server::bind(&([127, 0, 0, 1], 0).into())
+            .http2_only(true)
+            .serve(make_service);
+        let addr = server.local_addr();
+        let server_handle = tokio::spawn(server);
+
+        let mut config = create_test_config();
+        config.server_url = format!("http://{}", addr);
+        config.http2_prior_knowledge = true;
+        config.http2_max_concurrent_streams = Some(16);
+        let client = Arc::new(HttpClient::new(config));
+
+        let mut handles = vec![];
+        for _ in 0..4 {
+            let client_clone = Arc::clone(&client);
+            let events = create_test_events(1);
+            handles.push(tokio::spawn(async move {
+                client_clone.send_event_batch(&events, "test-token").await
+            }));
+        }
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+
+        // All four batches were multiplexed over a single h2 connection
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+        assert_eq!(h2_requests.load(Ordering::SeqCst), 4);
+
+        let metrics = client.get_connection_metrics().await;
+        assert_eq!(metrics.connections_created, 1);
+        assert_eq!(metrics.h2_requests, 4);
+
+        server_handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_response_schema_validation() {
+        let mut config = create_test_config();
+        config.validate_response_schema = true;
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        // Ack is missing the required "status" field
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"events_processed": 1}"#)
+            .create();
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(matches!(result, Err(ClientError::SchemaMismatch(_))));
+        assert!(result.unwrap_err().to_string().contains("status"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_preempts_event_backlog() {
+        let mut config = create_test_config();
+        config.max_concurrent_requests = 1;
+        let client = Arc::new(HttpClient::new(config));
+
+        let _events_mock = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body_from_fn(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(500));
+                std::io::Write::write_all(w, br#"{"status": "success"}"#)
+            })
+            .create();
+        let _heartbeat_mock = mock("POST", "/api/v1/heartbeat")
+            .with_status(200)
+            .with_body(r#"{"status": "acknowledged"}"#)
+            .create();
+
+        // Saturate the single slot with a backlog of slow event sends
+        for _ in 0..5 {
+            let client_clone = Arc::clone(&client);
+            let events = create_test_events(1);
+            tokio::spawn(async move { client_clone.send_event_batch(&events, "test-token").await });
+        }
+        tokio::task::yield_now().await;
+
+        let heartbeat = create_test_heartbeat();
+        let sent = tokio::time::timeout(
+            tokio::time::Duration::from_secs(1),
+            client.send_heartbeat(&heartbeat, "test-token"),
+        )
+        .await;
+        assert!(sent.is_ok(), "heartbeat should not wait behind the whole backlog");
+    }
+
+    #[tokio::test]
+    async fn test_msgpack_encode_failure_skips_event() {
+        // Serializes like the wrapped event, except the poisoned one fails to encode
+        struct PoisonedEvent<'a>(&'a TelemetryEvent);
+
+        impl serde::Serialize for PoisonedEvent<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                if self.0.id == "test-event-1" {
+                    return Err(serde::ser::Error::custom("unencodable value"));
+                }
+                self.0.serialize(serializer)
+            }
+        }
+
+        let mut config = create_test_config();
+        config.wire_format = WireFormat::MessagePack;
+        config.encode_failure_policy = EncodeFailurePolicy::SkipEvent;
+        let client = HttpClient::new(config).with_event_encoder(Box::new(|event: &TelemetryEvent| {
+            rmp_serde::to_vec_named(&PoisonedEvent(event)).map_err(EncodeError::from)
+        }));
+        let events = create_test_events(3);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .match_header("Content-Type", "application/msgpack")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        let (body, skipped) = client.encode_batch(&events).unwrap();
+        assert_eq!(skipped, vec!["test-event-1".to_string()]);
+
+        let envelope: BatchEnvelope = rmp_serde::from_slice(&body).unwrap();
+        assert_eq!(envelope.event_count, 2);
+        let ids: Vec<_> = envelope.events.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids, vec!["test-event-0", "test-event-2"]);
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_registration_capability_pruning() {
+        let mut config = create_test_config();
+        config.max_registration_bytes = Some(1024);
+        let client = HttpClient::new(config);
+
+        let mut capabilities = vec!["process_monitoring".to_string(), "file_monitoring".to_string()];
+        capabilities.extend((0..200).map(|i| format!("optional_capability_{}", i)));
+        let registration = AgentRegistration {
+            agent_id: "test-agent-123".to_string(),
+            tenant_id: "test-tenant".to_string(),
+            hostname: "test-host".to_string(),
+            os: "Linux".to_string(),
+            version: "1.0.0".to_string(),
+            capabilities,
+        };
+
+        let payload = client.prepare_registration(&registration).unwrap();
+        assert!(payload.len() <= 1024);
+
+        let sent: AgentRegistration = serde_json::from_slice(&payload).unwrap();
+        assert!(sent.capabilities.contains(&"process_monitoring".to_string()));
+        assert!(sent.capabilities.contains(&"file_monitoring".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_per_tenant_endpoint_routing() {
+        let mut config = create_test_config();
+        config.tenant_endpoints.insert("tenant-a".to_string(), format!("{}/cluster-a", mockito::server_url()));
+        config.tenant_endpoints.insert("tenant-b".to_string(), format!("{}/cluster-b", mockito::server_url()));
+        let client = HttpClient::new(config);
+
+        let _tenant_a = mock("POST", "/cluster-a/api/v1/events")
+            .match_header("X-Tenant-ID", "tenant-a")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect(1)
+            .create();
+        let _tenant_b = mock("POST", "/cluster-b/api/v1/events")
+            .match_header("X-Tenant-ID", "tenant-b")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect(1)
+            .create();
+
+        let events = create_test_events(1);
+        assert!(client.send_event_batch_for_tenant("tenant-a", &events, "test-token").await.is_ok());
+        assert!(client.send_event_batch_for_tenant("tenant-b", &events, "test-token").await.is_ok());
+
+        _tenant_a.assert();
+        _tenant_b.assert();
+    }
+
+    #[tokio::test]
+    async fn test_cross_host_redirect_strips_auth() {
+        let events = create_test_events(1);
+
+        // The same mock server reached under a different host name
+        let cross_host = format!(
+            "http://localhost:{}/other-host/api/v1/events",
+            mockito::server_address().port()
+        );
+        let _redirect = mock("POST", "/api/v1/events")
+            .with_status(307)
+            .with_header("Location", &cross_host)
+            .create();
+        let _target = mock("POST", "/other-host/api/v1/events")
+            .match_header("Authorization", Matcher::Missing)
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect(1)
+            .create();
+
+        // SameHost does not follow the redirect at all
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.follow_redirects = RedirectPolicy::SameHost;
+        let client = HttpClient::new(config.clone());
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_err());
+        let redirects = client.get_request_metrics().await.redirects;
+        assert_eq!(redirects.followed, 0);
+        assert_eq!(redirects.blocked_cross_host, 1);
+
+        // Limited follows across hosts but never forwards the bearer token
+        config.follow_redirects = RedirectPolicy::Limited(3);
+        let client = HttpClient::new(config);
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+        _target.assert();
+    }
+
+    #[tokio::test]
+    async fn test_max_outstanding_batches() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.max_outstanding_batches = 2;
+        let client = Arc::new(HttpClient::new(config));
+
+        let _mock = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body_from_fn(|w| {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                std::io::Write::write_all(w, br#"{"status": "success"}"#)
+            })
+            .expect(6)
+            .create();
+
+        let mut handles = vec![];
+        for _ in 0..6 {
+            let client_clone = Arc::clone(&client);
+            let events = create_test_events(1);
+            handles.push(tokio::spawn(async move {
+                client_clone.send_event_batch(&events, "test-token").await
+            }));
+        }
+        for handle in handles {
+            assert!(handle.await.unwrap().is_ok());
+        }
+
+        _mock.assert();
+        let metrics = client.get_request_metrics().await;
+        // The limit caps concurrency without serializing the sends
+        assert_eq!(metrics.peak_outstanding_batches, 2);
+    }
+
+    #[tokio::test]
+    async fn test_stdout_transport_ndjson() {
+        let mut output = Vec::new();
+        let transport = StdoutTransport::with_writer(StdoutFormat::Ndjson, &mut output);
+        let events = create_test_events(3);
+
+        transport.send(&events).await.unwrap();
+
+        let printed = String::from_utf8(output).unwrap();
+        let lines: Vec<_> = printed.lines().collect();
+        assert_eq!(lines.len(), 3);
+        for (line, event) in lines.iter().zip(&events) {
+            let parsed: TelemetryEvent = serde_json::from_str(line).unwrap();
+            assert_eq!(parsed.id, event.id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_configuration_sync_etag() {
+        let config = create_test_config();
+        let client = HttpClient::new(config);
+
+        let _first = mock("GET", "/api/v1/agents/config")
+            .with_status(200)
+            .with_header("ETag", "\"v1\"")
+            .with_body(r#"{"collection_interval": 45, "max_batch_size": 150, "enable_compression": true, "rules": []}"#)
+            .create();
+
+        let result = client.sync_configuration("test-token").await;
+        assert_eq!(result.unwrap().collection_interval, 45);
+        assert_eq!(client.config_etag().await, Some("\"v1\"".to_string()));
+
+        let _second = mock("GET", "/api/v1/agents/config")
+            .match_header("If-None-Match", "\"v1\"")
+            .with_status(304)
+            .create();
+
+        let result = client.sync_configuration_if_changed("test-token").await;
+        assert!(matches!(result, Ok(ConfigSync::NotModified)));
+        assert_eq!(client.config_etag().await, Some("\"v1\"".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_dns_failure_retry_policy() {
+        let mut config = create_test_config();
+        config.server_url = "https://unresolvable.insec.invalid".to_string();
+        config.dns_retry_attempts = 2;
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let result = client.send_event_batch_with_retry(&events, "test-token").await;
+        assert!(matches!(result, Err(ClientError::DnsResolution(_))));
+
+        let metrics = client.get_request_metrics().await;
+        assert_eq!(metrics.dns_retries, 2);
+        assert_eq!(metrics.total_requests, 3); // Initial + DNS-specific retries
+    }
+
+    #[tokio::test]
+    async fn test_compressed_heartbeat_with_samples() {
+        let mut config = create_test_config();
+        config.enable_compression = true;
+        let client = HttpClient::new(config);
+
+        let mut heartbeat = create_test_heartbeat();
+        heartbeat.samples = (0..500)
+            .map(|_| PerformanceMetrics { timestamp: Utc::now(), cpu_usage_percent: 15.2, memory_usage_mb: 50.5 })
+            .collect();
+
+        let _mock = mock("POST", "/api/v1/heartbeat")
+            .match_header("Content-Encoding", "gzip")
+            .with_status(200)
+            .with_body(r#"{"status": "acknowledged"}"#)
+            .create();
+
+        let request = client.prepare_heartbeat(&heartbeat, "test-token").unwrap();
+        let decoded: HeartbeatData = serde_json::from_slice(&request.decompressed_body().unwrap()).unwrap();
+        assert_eq!(decoded.samples.len(), 500);
+
+        let result = client.send_heartbeat(&heartbeat, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_streaming_config_sync() {
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let client = HttpClient::new(config);
+
+        let _stream = mock("GET", "/api/v1/agents/config/stream")
+            .match_header("Accept", "text/event-stream")
+            .with_status(200)
+            .with_header("Content-Type", "text/event-stream")
+            .with_body("event: config\ndata: {\"collection_interval\": 90}\n\n")
+            .create();
+        let _poll = mock("GET", "/api/v1/agents/config")
+            .expect(0)
+            .create();
+
+        let mut updates = client.stream_configuration("test-token").await.unwrap();
+        let update = futures::StreamExt::next(&mut updates).await.unwrap().unwrap();
+        assert_eq!(update.collection_interval, 90);
+        _poll.assert();
+    }
+
+    #[tokio::test]
+    async fn test_weak_server_key_rejected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+
+        // Checked-in fixture: self-signed for localhost with a 1024-bit RSA key
+        let weak_identity = TestCertificate::from_pem(
+            include_str!("../../fixtures/tls/weak-server-rsa1024.crt"),
+            include_str!("../../fixtures/tls/weak-server-rsa1024.key"),
+        );
+        let server = MockTlsServer::start(&weak_identity, &[]).await;
+
+        let mut config = create_test_config();
+        config.server_url = server.url();
+        config.tls_ca_cert = Some(weak_identity.write_pem(temp_dir.path(), "weak-ca").0);
+        let events = create_test_events(1);
+
+        config.tls_min_rsa_bits = 2048;
+        let result = HttpClient::new(config.clone()).send_event_batch(&events, "test-token").await;
+        assert!(matches!(result, Err(ClientError::WeakServerCertificate(_))));
+
+        // The same trusted server is accepted once the minimum allows its key size
+        config.tls_min_rsa_bits = 1024;
+        let result = HttpClient::new(config).send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_zstd_dictionary_compression() {
+        let samples: Vec<Vec<u8>> = (0..200)
+            .map(|i| serde_json::to_vec(&create_test_events(1 + i % 3)).unwrap())
+            .collect();
+        let dictionary = zstd::dict::from_samples(&samples, 4096).unwrap();
+
+        let events = create_test_events(2);
+        let plain = ZstdCompressor::new(3).compress(&serde_json::to_vec(&events).unwrap()).unwrap();
+        let with_dict = ZstdCompressor::with_dictionary(3, dictionary)
+            .compress(&serde_json::to_vec(&events).unwrap())
+            .unwrap();
+
+        assert!(with_dict.len() < plain.len());
+    }
+
+    #[tokio::test]
+    async fn test_failed_heartbeat_reconnects_transport() {
+        let config = create_test_config();
+        let client = HttpClient::new(config);
+
+        let heartbeat = create_test_heartbeat();
+
+        let _heartbeat_mock = mock("POST", "/api/v1/heartbeat")
+            .with_status(503)
+            .create();
+        let _events_mock = mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        let before = client.get_connection_metrics().await.connections_created;
+        assert!(client.send_heartbeat(&heartbeat, "test-token").await.is_err());
+
+        let events = create_test_events(1);
+        assert!(client.send_event_batch(&events, "test-token").await.is_ok());
+
+        let metrics = client.get_connection_metrics().await;
+        assert_eq!(metrics.transport_reconnects, 1);
+        assert!(metrics.connections_created > before);
+    }
+
+    #[tokio::test]
+    async fn test_field_type_coercion() {
+        let mut config = create_test_config();
+        config.field_coercions.insert("pid".to_string(), JsonType::String);
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let body = client.serialize_batch(&events).unwrap();
+
+        assert_eq!(body["events"][0]["data"]["pid"], serde_json::Value::String("0".to_string()));
+        assert!(events[0].data["pid"].is_number());
+    }
+
+    #[tokio::test]
+    async fn test_custom_compression_codec() {
+        struct ReverseCodec;
+
+        impl Compressor for ReverseCodec {
+            fn compress(&self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+                Ok(input.iter().rev().copied().collect())
+            }
+
+            fn decompress(&self, input: &[u8]) -> std::io::Result<Vec<u8>> {
+                Ok(input.iter().rev().copied().collect())
+            }
+
+            fn content_encoding(&self) -> &'static str {
+                "x-reverse"
+            }
+        }
+
+        register_compressor("reverse", Arc::new(ReverseCodec));
+        let mut config = create_test_config();
+        config.compression = Some("reverse".to_string());
+        let client = HttpClient::new(config);
+        let events = create_test_events(2);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .match_header("Content-Encoding", "x-reverse")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        let codec = lookup_compressor("reverse").unwrap();
+        let payload = serde_json::to_vec(&events).unwrap();
+        assert_eq!(codec.decompress(&codec.compress(&payload).unwrap()).unwrap(), payload);
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_sigv4_request_signing() {
+        let mut config = create_test_config();
+        config.auth_method = AuthMethod::SigV4 {
+            region: "us-east-1".to_string(),
+            service: "execute-api".to_string(),
+            access_key: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+        };
+        let client = HttpClient::new(config);
+        let events = create_test_events(1);
+
+        let _mock = mock("POST", "/api/v1/events")
+            .match_header("Authorization", Matcher::Regex(
+                r"^AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/\d{8}/us-east-1/execute-api/aws4_request, SignedHeaders=[a-z0-9;-]+, Signature=[0-9a-f]{64}$".to_string(),
+            ))
+            .match_header("X-Amz-Date", Matcher::Regex(r"^\d{8}T\d{6}Z$".to_string()))
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        let result = client.send_event_batch(&events, "test-token").await;
+        assert!(result.is_ok());
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_named_pipe_transport_round_trip() {
+        use tokio::io::AsyncBufReadExt;
+        use tokio::net::windows::named_pipe::ServerOptions;
+
+        let pipe_name = r"\\.\pipe\insec-test-events";
+        let server = ServerOptions::new().first_pipe_instance(true).create(pipe_name).unwrap();
+        let reader = tokio::spawn(async move {
+            server.connect().await.unwrap();
+            let mut lines = tokio::io::BufReader::new(server).lines();
+            let mut received = Vec::new();
+            for _ in 0..2 {
+                received.push(lines.next_line().await.unwrap().unwrap());
+            }
+            received
+        });
+
+        let transport = NamedPipeTransport::new(pipe_name, StdoutFormat::Ndjson);
+        let events = create_test_events(2);
+        transport.send(&events).await.unwrap();
+
+        let received = reader.await.unwrap();
+        let parsed: TelemetryEvent = serde_json::from_str(&received[0]).unwrap();
+        assert_eq!(parsed.id, "test-event-0");
+        assert_eq!(received.len(), 2);
+    }
+
     // Benchmark tests
     #[bench]
     fn bench_event_serialization(b: &mut test::Bencher) {
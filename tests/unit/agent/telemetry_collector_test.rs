@@ -34,6 +34,60 @@ mod tests {
         }
     }
 
+    #[derive(Clone)]
+    struct MockClock {
+        now: Arc<std::sync::Mutex<chrono::DateTime<Utc>>>,
+    }
+
+    impl MockClock {
+        fn new(start: chrono::DateTime<Utc>) -> Self {
+            Self {
+                now: Arc::new(std::sync::Mutex::new(start)),
+            }
+        }
+
+        fn advance(&self, by: Duration) {
+            let mut now = self.now.lock().unwrap();
+            *now = *now + by;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> chrono::DateTime<Utc> {
+            *self.now.lock().unwrap()
+        }
+    }
+
+    // Records each requested sleep and holds it until the test releases it
+    #[derive(Clone)]
+    struct MockSleeper {
+        requested: tokio::sync::mpsc::UnboundedSender<std::time::Duration>,
+        release: Arc<tokio::sync::Semaphore>,
+    }
+
+    impl MockSleeper {
+        fn new() -> (Self, tokio::sync::mpsc::UnboundedReceiver<std::time::Duration>) {
+            let (requested, rx) = tokio::sync::mpsc::unbounded_channel();
+            let sleeper = Self {
+                requested,
+                release: Arc::new(tokio::sync::Semaphore::new(0)),
+            };
+            (sleeper, rx)
+        }
+
+        fn release(&self) {
+            self.release.add_permits(1);
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Sleeper for MockSleeper {
+        async fn sleep(&self, duration: std::time::Duration) {
+            let _ = self.requested.send(duration);
+            self.release.acquire().await.unwrap().forget();
+        }
+    }
+
     // Test data factories
     fn create_test_config() -> Config {
         Config {
@@ -45,6 +99,7 @@ mod tests {
             tls_ca_cert: None,
             tls_client_cert: None,
             tls_client_key: None,
+            ..Default::default()
         }
     }
 
@@ -361,10 +416,11 @@ mod tests {
         let collector = TelemetryCollector::new(config);
 
         // Act
-        let is_protected = collector.check_self_protection().await;
+        let protection = collector.check_self_protection().await;
 
         // Assert
-        assert!(is_protected); // Agent should be self-protected
+        assert!(protection.is_protected()); // Agent should be self-protected
+        assert_ne!(protection.integrity, IntegrityStatus::Tampered);
     }
 
     #[tokio::test]
@@ -409,6 +465,1479 @@ mod tests {
         assert!(*cpu_usage >= 0.0 && *cpu_usage <= 100.0);
     }
 
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_disk_queue_segment_rotation_cap() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = create_test_config();
+        config.max_queue_segments = 2;
+        config.segment_max_bytes = 1024;
+        let store = FileEventStore::open(temp_dir.path(), &config).await.unwrap();
+
+        // Act - each event is large enough to fill its own segment
+        for i in 0..4 {
+            let mut event = create_test_file_event();
+            event.id = format!("segment-event-{}", i);
+            event.data.insert("padding".to_string(), serde_json::Value::String("x".repeat(1024)));
+            store.append(event).await.unwrap();
+        }
+
+        // Assert
+        assert_eq!(store.segment_count().await, 2);
+        assert_eq!(store.dropped_segment_count(), 2);
+        assert!(logs_contain("dropped 2 queue segments"));
+
+        let remaining = store.load_pending().await.unwrap();
+        assert!(remaining.iter().all(|e| e.id != "segment-event-0" && e.id != "segment-event-1"));
+        assert!(remaining.iter().any(|e| e.id == "segment-event-3"));
+    }
+
+    #[tokio::test]
+    async fn test_send_failure_delivers_hook() {
+        // Arrange
+        let mut config = create_test_config();
+        config.server_url = "https://nonexistent.invalid.server".to_string();
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+        let mut hooks = collector.on_event(EventHook::channel(16));
+
+        mock_collector.add_event(create_test_process_event()).await;
+
+        // Act
+        let result = collector.send_events(&mock_collector).await;
+
+        // Assert
+        assert!(result.is_err());
+        let hook = tokio::time::timeout(std::time::Duration::from_secs(5), hooks.recv())
+            .await
+            .expect("SendFailed hook was not delivered");
+        match hook {
+            Some(AgentEvent::SendFailed { error, .. }) => assert!(!error.is_empty()),
+            other => panic!("expected SendFailed hook, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_process_path_normalization() {
+        // Arrange
+        let mut config = create_test_config();
+        config.normalize_process_paths = true;
+        let collector = TelemetryCollector::new(config);
+
+        let mut upper = create_test_process_event();
+        upper.data.insert("path".to_string(), serde_json::Value::String(r"C:\Windows\System32\CMD.EXE".to_string()));
+        let mut lower = create_test_process_event();
+        lower.data.insert("path".to_string(), serde_json::Value::String(r"c:\windows\system32\cmd.exe".to_string()));
+
+        // Act
+        collector.normalize_process_path(&mut upper, TargetOs::Windows);
+        collector.normalize_process_path(&mut lower, TargetOs::Windows);
+
+        // Assert
+        assert_eq!(upper.data.get("path_normalized"), lower.data.get("path_normalized"));
+        assert_eq!(upper.data.get("path").unwrap().as_str().unwrap(), r"C:\Windows\System32\CMD.EXE");
+        assert_eq!(lower.data.get("path").unwrap().as_str().unwrap(), r"c:\windows\system32\cmd.exe");
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_carries_metrics_history() {
+        // Arrange
+        let config = create_test_config();
+        let collector = TelemetryCollector::new(config);
+
+        // Act
+        for _ in 0..3 {
+            collector.collect_performance_metrics().await;
+        }
+        let heartbeat = collector.build_heartbeat().await;
+
+        // Assert
+        assert!(heartbeat.samples.len() >= 2);
+        assert!(heartbeat.samples.len() <= collector.metrics_history_capacity());
+        for pair in heartbeat.samples.windows(2) {
+            assert!(pair[0].timestamp <= pair[1].timestamp);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_overlapping_collection_cycles_are_bounded() {
+        // Arrange
+        let mut config = create_test_config();
+        config.collection_interval = 1;
+        config.max_concurrent_cycles = 1;
+        let collector = TelemetryCollector::new(config);
+        collector.set_collection_delay(std::time::Duration::from_secs(5)); // Each cycle outlasts the interval
+
+        // Act
+        let handle = tokio::spawn({
+            let collector = collector.clone();
+            async move { collector.run_collection_loop().await }
+        });
+        for _ in 0..10 {
+            tokio::time::advance(std::time::Duration::from_secs(1)).await;
+            assert!(collector.running_cycles() <= 1);
+        }
+        handle.abort();
+
+        // Assert
+        assert!(collector.skipped_cycles() > 0);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "pcap"))]
+    #[tokio::test]
+    #[ignore] // Requires CAP_NET_RAW
+    async fn test_pcap_loopback_flow_event() {
+        // Arrange
+        let mut config = create_test_config();
+        config.pcap_interface = Some("lo".to_string());
+        config.pcap_filter = Some("icmp".to_string());
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        // Act
+        std::process::Command::new("ping").args(["-c", "1", "127.0.0.1"]).status().unwrap();
+        collector.collect_packet_telemetry(&mock_collector).await.unwrap();
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        let flow = events.iter()
+            .find(|e| matches!(e.event_type, EventType::Network)
+                && e.data.get("protocol") == Some(&serde_json::Value::String("icmp".to_string())))
+            .expect("loopback ping should produce a flow event");
+        assert_eq!(flow.data.get("src_ip").unwrap().as_str().unwrap(), "127.0.0.1");
+        assert!(flow.data.get("packet_count").unwrap().as_u64().unwrap() >= 1);
+        assert!(!flow.data.contains_key("payload"));
+    }
+
+    #[tokio::test]
+    async fn test_event_serialization_epoch_millis() {
+        // Arrange
+        let event = create_test_process_event();
+
+        // Act
+        let serialized = event.to_json_with(TimestampFormat::EpochMillis).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+        let deserialized = TelemetryEvent::from_json_with(&serialized, TimestampFormat::EpochMillis).unwrap();
+
+        // Assert
+        assert_eq!(value["timestamp"].as_i64().unwrap(), event.timestamp.timestamp_millis());
+        assert_eq!(deserialized.timestamp.timestamp_millis(), event.timestamp.timestamp_millis());
+        assert_eq!(event.id, deserialized.id);
+    }
+
+    #[tokio::test]
+    async fn test_rare_process_scores_higher_rarity() {
+        // Arrange
+        let collector = TelemetryCollector::new(create_test_config());
+        for _ in 0..50 {
+            let mut event = create_test_process_event();
+            collector.calculate_risk_score(&mut event).await;
+        }
+
+        let mut common = create_test_process_event();
+        let mut rare = create_test_process_event();
+        rare.data.insert("process_name".to_string(), serde_json::Value::String("never-seen.exe".to_string()));
+
+        // Act
+        collector.calculate_risk_score(&mut common).await;
+        collector.calculate_risk_score(&mut rare).await;
+
+        // Assert
+        let rarity = |e: &TelemetryEvent| e.metadata["risk_breakdown"]["rarity"].as_f64().unwrap();
+        assert!(rarity(&rare) > rarity(&common));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_non_utf8_filename_preserved_as_base64() {
+        use base64::Engine;
+        use std::os::unix::ffi::OsStrExt;
+
+        // Arrange
+        let raw: &[u8] = b"/tmp/insec_\xff\xfe_test.txt";
+        let path = std::path::Path::new(std::ffi::OsStr::from_bytes(raw));
+        let mut event = create_test_file_event();
+
+        // Act
+        event.set_path_field("filename", path);
+        let serialized = serde_json::to_string(&event).unwrap();
+        let deserialized: TelemetryEvent = serde_json::from_str(&serialized).unwrap();
+
+        // Assert
+        assert_eq!(deserialized.metadata.get("filename_encoding").unwrap().as_str().unwrap(), "base64");
+        let encoded = deserialized.data.get("filename").unwrap().as_str().unwrap();
+        let decoded = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert_eq!(decoded, raw);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_watchdog_aborts_stuck_collector() {
+        // Arrange
+        let mut config = create_test_config();
+        config.collection_interval = 1;
+        let collector = TelemetryCollector::new(config);
+        collector.register_collector("hanging", |_| async {
+            futures::future::pending::<anyhow::Result<()>>().await
+        });
+
+        // Act
+        let handle = tokio::spawn({
+            let collector = collector.clone();
+            async move { collector.run_collection_loop().await }
+        });
+        tokio::time::advance(std::time::Duration::from_secs(10)).await;
+        tokio::task::yield_now().await;
+
+        // Assert
+        let status = collector.get_status().await;
+        assert!(status.stalled_cycles >= 1);
+        assert!(status.completed_cycles >= 1); // Loop resumed after the abort
+        handle.abort();
+    }
+
+    #[tokio::test]
+    async fn test_min_collect_risk_score_discards_at_source() {
+        // Arrange
+        let mut config = create_test_config();
+        config.min_collect_risk_score = Some(0.2);
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        let mut low = create_test_file_event();
+        low.id = "low-risk-file".to_string();
+        low.metadata.insert("risk_score".to_string(), serde_json::json!(0.05));
+        let mut kept = create_test_file_event();
+        kept.id = "mid-risk-file".to_string();
+        kept.metadata.insert("risk_score".to_string(), serde_json::json!(0.5));
+
+        // Act
+        collector.enqueue_scored(&mock_collector, low).await;
+        collector.enqueue_scored(&mock_collector, kept).await;
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].id, "mid-risk-file");
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_events_aggregate_occurrences() {
+        // Arrange
+        let config = create_test_config();
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        let first = create_test_process_event();
+        for i in 0..3 {
+            let mut event = first.clone();
+            event.timestamp = first.timestamp + Duration::milliseconds(100 * i);
+            mock_collector.add_event(event).await;
+        }
+
+        // Act
+        collector.deduplicate_events(&mock_collector).await;
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metadata.get("occurrence_count").unwrap().as_u64().unwrap(), 3);
+        assert!(events[0].metadata.contains_key("first_seen"));
+        assert!(events[0].metadata.contains_key("last_seen"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_failing_collector_backs_off() {
+        // Arrange
+        let mut config = create_test_config();
+        config.collection_interval = 1;
+        config.collector_backoff_max_secs = 60;
+        let collector = TelemetryCollector::new(config);
+        let calls = Arc::new(Mutex::new(0u32));
+        collector.register_collector("proc_denied", {
+            let calls = Arc::clone(&calls);
+            move |_| {
+                let calls = Arc::clone(&calls);
+                async move {
+                    *calls.lock().await += 1;
+                    Err(anyhow::anyhow!("permission denied reading /proc"))
+                }
+            }
+        });
+
+        // Act
+        let handle = tokio::spawn({
+            let collector = collector.clone();
+            async move { collector.run_collection_loop().await }
+        });
+        tokio::time::advance(std::time::Duration::from_secs(30)).await;
+        let early_calls = *calls.lock().await;
+        tokio::time::advance(std::time::Duration::from_secs(30)).await;
+        let late_calls = *calls.lock().await - early_calls;
+        handle.abort();
+
+        // Assert
+        assert!(early_calls < 30);
+        assert!(late_calls < early_calls);
+        let status = collector.get_status().await;
+        assert!(status.collector_backoff.contains_key("proc_denied"));
+    }
+
+    #[tokio::test]
+    async fn test_process_event_to_ocsf() {
+        // Arrange
+        let event = create_test_process_event();
+
+        // Act
+        let ocsf = event.to_ocsf();
+
+        // Assert
+        assert_eq!(ocsf["class_uid"], 1007); // Process Activity
+        assert_eq!(ocsf["category_uid"], 1); // System Activity
+        assert_eq!(ocsf["activity_id"], 1); // Launch
+        assert_eq!(ocsf["process"]["name"], "test.exe");
+        assert_eq!(ocsf["process"]["pid"], 1234);
+        assert!(ocsf.get("time").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_binary_integrity_mismatch_reported() {
+        // Arrange
+        let mut config = create_test_config();
+        config.expected_binary_sha256 = Some("0".repeat(64));
+        let collector = TelemetryCollector::new(config);
+
+        // Act
+        let protection = collector.check_self_protection().await;
+        let heartbeat = collector.build_heartbeat().await;
+
+        // Assert
+        assert_eq!(protection.integrity, IntegrityStatus::Tampered);
+        assert!(!protection.is_protected());
+        assert_eq!(heartbeat.integrity, IntegrityStatus::Tampered);
+    }
+
+    #[tokio::test]
+    async fn test_binary_integrity_unreadable_binary() {
+        // Arrange
+        let mut config = create_test_config();
+        config.expected_binary_sha256 = Some("0".repeat(64));
+        config.agent_binary_path = Some("/nonexistent/insec-agent".to_string());
+        let collector = TelemetryCollector::new(config);
+
+        // Act
+        let protection = collector.check_self_protection().await;
+
+        // Assert
+        assert_eq!(protection.integrity, IntegrityStatus::Unreadable);
+        assert!(!protection.is_protected());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_scheduled_task_telemetry_from_crontab() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let crontab = temp_dir.path().join("root");
+        tokio::fs::write(&crontab, "").await.unwrap();
+
+        let mut config = create_test_config();
+        config.crontab_paths = vec![temp_dir.path().to_string_lossy().to_string()];
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+        collector.collect_scheduled_task_telemetry(&mock_collector).await.unwrap();
+
+        // Act
+        tokio::fs::write(&crontab, "*/5 * * * * /tmp/payload.sh\n").await.unwrap();
+        collector.collect_scheduled_task_telemetry(&mock_collector).await.unwrap();
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        let task = events.iter()
+            .find(|e| matches!(e.event_type, EventType::ScheduledTask))
+            .expect("new cron entry should produce a scheduled task event");
+        assert_eq!(task.data.get("command").unwrap().as_str().unwrap(), "/tmp/payload.sh");
+        assert_eq!(task.data.get("schedule").unwrap().as_str().unwrap(), "*/5 * * * *");
+        assert_eq!(task.data.get("principal").unwrap().as_str().unwrap(), "root");
+        assert!(task.data.contains_key("name"));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "device-monitoring"))]
+    #[tokio::test]
+    async fn test_udev_event_parsed_into_device_event() {
+        // Arrange
+        let add = "ACTION=add\0SUBSYSTEM=block\0DEVNAME=/dev/sdb1\0ID_VENDOR_ID=0781\0ID_MODEL_ID=5581\0ID_SERIAL_SHORT=4C530001\0";
+        let remove = "ACTION=remove\0SUBSYSTEM=block\0DEVNAME=/dev/sdb1\0ID_VENDOR_ID=0781\0ID_MODEL_ID=5581\0ID_SERIAL_SHORT=4C530001\0";
+        let mounts = "/dev/sda1 / ext4 rw,relatime 0 0\n/dev/sdb1 /media/usb0 vfat rw,nosuid,nodev 0 0\n";
+
+        // Act - the mount point comes from the mount table, not the uevent
+        let mut added = parse_udev_event(add.as_bytes()).unwrap();
+        attach_mount_point(&mut added, mounts);
+        let mut removed = parse_udev_event(remove.as_bytes()).unwrap();
+        attach_mount_point(&mut removed, "/dev/sda1 / ext4 rw,relatime 0 0\n");
+
+        // Assert
+        assert!(matches!(added.event_type, EventType::Device));
+        assert_eq!(added.data.get("action").unwrap().as_str().unwrap(), "add");
+        assert_eq!(added.data.get("vendor_id").unwrap().as_str().unwrap(), "0781");
+        assert_eq!(added.data.get("product_id").unwrap().as_str().unwrap(), "5581");
+        assert_eq!(added.data.get("serial").unwrap().as_str().unwrap(), "4C530001");
+        assert_eq!(added.data.get("mount_point").unwrap().as_str().unwrap(), "/media/usb0");
+
+        assert!(matches!(removed.event_type, EventType::Device));
+        assert_eq!(removed.data.get("action").unwrap().as_str().unwrap(), "remove");
+        assert!(!removed.data.contains_key("mount_point"));
+    }
+
+    #[tokio::test]
+    async fn test_sampling_seed_is_reproducible() {
+        // Arrange
+        let mut config = create_test_config();
+        config.sample_rate = 0.5;
+        config.sampling_seed = Some(42);
+        let first = TelemetryCollector::new(config.clone());
+        let second = TelemetryCollector::new(config);
+
+        let events: Vec<TelemetryEvent> = (0..100)
+            .map(|i| {
+                let mut event = create_test_process_event();
+                event.id = format!("sampled-event-{}", i);
+                event
+            })
+            .collect();
+
+        // Act
+        let kept_first: Vec<_> = events.iter().filter(|e| first.should_sample(e)).map(|e| e.id.clone()).collect();
+        let kept_second: Vec<_> = events.iter().filter(|e| second.should_sample(e)).map(|e| e.id.clone()).collect();
+
+        // Assert
+        assert_eq!(kept_first, kept_second);
+        assert!(!kept_first.is_empty() && kept_first.len() < events.len());
+    }
+
+    #[tokio::test]
+    async fn test_risk_score_calibration() {
+        // Arrange
+        let mut config = create_test_config();
+        config.risk_calibration = Some(RiskCalibration::MinMax { min: 0.0, max: 10.0 });
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        let mut high = create_test_process_event();
+        high.metadata.insert("risk_score".to_string(), serde_json::json!(8.0));
+        let mut low = create_test_file_event();
+        low.metadata.insert("risk_score".to_string(), serde_json::json!(2.0));
+
+        // Act
+        collector.calibrate_risk_score(&mut high);
+        collector.calibrate_risk_score(&mut low);
+        mock_collector.add_event(high).await;
+        mock_collector.add_event(low).await;
+        let filtered_events = collector.filter_events(&mock_collector, 0.5).await;
+
+        // Assert
+        assert_eq!(filtered_events.len(), 1);
+        let risk_score = filtered_events[0].metadata.get("risk_score").unwrap().as_f64().unwrap();
+        assert!((risk_score - 0.8).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn test_drain_and_exit_flushes_disk_queue() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.queue_dir = Some(temp_dir.path().to_string_lossy().to_string());
+
+        let store = FileEventStore::open(temp_dir.path(), &config).await.unwrap();
+        for i in 0..5 {
+            let mut event = create_test_process_event();
+            event.id = format!("queued-event-{}", i);
+            store.append(event).await.unwrap();
+        }
+        drop(store);
+
+        let _events_mock = mockito::mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+        let _deregister_mock = mockito::mock("DELETE", "/api/v1/agents/test-agent-123")
+            .with_status(204)
+            .expect(1)
+            .create();
+
+        // Act
+        let collector = TelemetryCollector::new(config.clone());
+        let summary = collector.drain_and_exit(std::time::Duration::from_secs(10)).await.unwrap();
+
+        // Assert
+        assert_eq!(summary.sent, 5);
+        assert_eq!(summary.remaining, 0);
+        let store = FileEventStore::open(temp_dir.path(), &config).await.unwrap();
+        assert!(store.load_pending().await.unwrap().is_empty());
+        _deregister_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_command_line_hash_grouping() {
+        // Arrange
+        let mut config = create_test_config();
+        config.command_line_policy = CommandLinePolicy::HashOnly;
+        let collector = TelemetryCollector::new(config);
+
+        let mut first = create_test_process_event();
+        let mut same = create_test_process_event();
+        let mut different = create_test_process_event();
+        different.data.insert("command_line".to_string(), serde_json::Value::String("test.exe --other".to_string()));
+
+        // Act
+        collector.apply_command_line_policy(&mut first);
+        collector.apply_command_line_policy(&mut same);
+        collector.apply_command_line_policy(&mut different);
+
+        // Assert
+        let hash = |e: &TelemetryEvent| e.data.get("command_line_hash").unwrap().as_str().unwrap().to_string();
+        assert_eq!(hash(&first), hash(&same));
+        assert_ne!(hash(&first), hash(&different));
+        assert_eq!(hash(&first).len(), 64);
+        assert!(!first.data.contains_key("command_line"));
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_ack_requests_reregistration() {
+        // Arrange
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+        mock_collector.add_event(create_test_process_event()).await;
+
+        let _heartbeat_mock = mockito::mock("POST", "/api/v1/heartbeat")
+            .with_status(200)
+            .with_body(r#"{"status": "acknowledged", "reregister": true}"#)
+            .create();
+        let _register_mock = mockito::mock("POST", "/api/v1/agents/register")
+            .with_status(201)
+            .with_body(r#"{"status": "registered", "agent_id": "test-agent-123"}"#)
+            .expect(1)
+            .create();
+        let _events_mock = mockito::mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .create();
+
+        // Act
+        collector.send_heartbeat().await.unwrap();
+        collector.send_events(&mock_collector).await.unwrap();
+
+        // Assert
+        _register_mock.assert();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_repeated_errors_are_coalesced() {
+        // Arrange
+        let sink = Arc::new(Mutex::new(Vec::new()));
+        let logger = CoalescingLogger::new(std::time::Duration::from_secs(60), Arc::clone(&sink));
+
+        // Act
+        for _ in 0..100 {
+            logger.error("send failed", "connection refused").await;
+        }
+        tokio::time::advance(std::time::Duration::from_secs(61)).await;
+        logger.flush().await;
+
+        // Assert
+        let lines = sink.lock().await;
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].contains("connection refused"));
+        assert!(lines[1].contains("send failed 99 times in the last 60s"));
+    }
+
+    #[tokio::test]
+    async fn test_recency_weight_batch_selection() {
+        // Arrange
+        let mock_collector = MockTelemetryCollector::new();
+        let now = Utc::now();
+        for i in 0..15 {
+            let mut event = create_test_process_event();
+            event.id = format!("test-event-{}", i);
+            event.timestamp = now - Duration::seconds(15 - i);
+            mock_collector.add_event(event).await;
+        }
+
+        let mut newest_first = create_test_config();
+        newest_first.recency_weight = 1.0;
+        let mut oldest_first = create_test_config();
+        oldest_first.recency_weight = 0.0;
+
+        // Act
+        let newest = TelemetryCollector::new(newest_first).create_batches(&mock_collector).await;
+        let oldest = TelemetryCollector::new(oldest_first).create_batches(&mock_collector).await;
+
+        // Assert
+        assert_eq!(newest[0][0].id, "test-event-14");
+        assert_eq!(oldest[0][0].id, "test-event-0");
+    }
+
+    #[tokio::test]
+    async fn test_always_collect_processes_bypass_filters() {
+        // Arrange
+        let mut config = create_test_config();
+        config.sample_rate = 0.0;
+        config.min_collect_risk_score = Some(0.9);
+        config.always_collect_processes = vec!["powershell.exe".to_string()];
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        let mut powershell = create_test_process_event();
+        powershell.id = "powershell-event".to_string();
+        powershell.data.insert("process_name".to_string(), serde_json::Value::String("powershell.exe".to_string()));
+        let other = create_test_process_event();
+
+        // Act
+        collector.enqueue_scored(&mock_collector, powershell).await;
+        collector.enqueue_scored(&mock_collector, other).await;
+        let filtered_events = collector.filter_events(&mock_collector, 0.9).await;
+
+        // Assert
+        assert_eq!(filtered_events.len(), 1);
+        assert_eq!(filtered_events[0].id, "powershell-event");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_ingest_file_replay_rate_limit() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dump = temp_dir.path().join("backfill.ndjson");
+        let lines: Vec<String> = (0..1000)
+            .map(|i| {
+                let mut event = create_test_process_event();
+                event.id = format!("replay-event-{}", i);
+                serde_json::to_string(&event).unwrap()
+            })
+            .collect();
+        tokio::fs::write(&dump, lines.join("\n")).await.unwrap();
+
+        let collector = TelemetryCollector::new(create_test_config());
+        let mock_collector = MockTelemetryCollector::new();
+
+        // Act
+        let started = tokio::time::Instant::now();
+        collector.ingest_file(&dump, &mock_collector, Some(100)).await.unwrap();
+        let elapsed = started.elapsed();
+
+        // Assert
+        assert_eq!(mock_collector.get_events().await.len(), 1000);
+        assert!(elapsed >= std::time::Duration::from_secs(9));
+        assert!(elapsed <= std::time::Duration::from_secs(11));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_kernel_module_diff_detection() {
+        // Arrange
+        let before = "ext4 749568 1 - Live 0x0000000000000000\n";
+        let after = "ext4 749568 1 - Live 0x0000000000000000\ninsec_dummy 16384 0 - Live 0x0000000000000000 (OE)\n";
+
+        // Act
+        let events = diff_kernel_modules(before, after);
+
+        // Assert
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert!(matches!(event.event_type, EventType::KernelModule));
+        assert!(event.id.starts_with("kmod-"));
+        assert_eq!(event.data.get("module_name").unwrap().as_str().unwrap(), "insec_dummy");
+        assert_eq!(event.data.get("signature_status").unwrap().as_str().unwrap(), "unsigned");
+        assert!(event.data.contains_key("path"));
+    }
+
+    #[tokio::test]
+    async fn test_dedup_scope_process_vs_host() {
+        // Arrange
+        let first = create_test_process_event();
+        let mut other_pid = first.clone();
+        other_pid.data.insert("pid".to_string(), serde_json::Value::Number(5678.into()));
+
+        let mut process_scope = create_test_config();
+        process_scope.dedup_scope = DedupScope::Process;
+        let mut host_scope = create_test_config();
+        host_scope.dedup_scope = DedupScope::Host;
+
+        // Act
+        let per_process = MockTelemetryCollector::new();
+        per_process.add_event(first.clone()).await;
+        per_process.add_event(other_pid.clone()).await;
+        TelemetryCollector::new(process_scope).deduplicate_events(&per_process).await;
+
+        let per_host = MockTelemetryCollector::new();
+        per_host.add_event(first).await;
+        per_host.add_event(other_pid).await;
+        TelemetryCollector::new(host_scope).deduplicate_events(&per_host).await;
+
+        // Assert
+        assert_eq!(per_process.get_events().await.len(), 2);
+        assert_eq!(per_host.get_events().await.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_parallel_enrichment_preserves_order() {
+        // Arrange
+        let events: Vec<TelemetryEvent> = (0..20)
+            .map(|i| {
+                let mut event = create_test_file_event();
+                event.id = format!("enrich-event-{}", i);
+                event
+            })
+            .collect();
+        let slow_enricher = |event: TelemetryEvent| async move {
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            event
+        };
+
+        let mut serial_config = create_test_config();
+        serial_config.enrichment_parallelism = 1;
+        let mut parallel_config = create_test_config();
+        parallel_config.enrichment_parallelism = 4;
+
+        // Act
+        let started = tokio::time::Instant::now();
+        TelemetryCollector::new(serial_config).enrich_events(events.clone(), slow_enricher).await;
+        let serial_elapsed = started.elapsed();
+
+        let started = tokio::time::Instant::now();
+        let enriched = TelemetryCollector::new(parallel_config).enrich_events(events.clone(), slow_enricher).await;
+        let parallel_elapsed = started.elapsed();
+
+        // Assert
+        assert!(parallel_elapsed * 3 < serial_elapsed);
+        let ids: Vec<_> = enriched.iter().map(|e| e.id.clone()).collect();
+        let expected: Vec<_> = events.iter().map(|e| e.id.clone()).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn test_crash_marker_triggers_safe_mode() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = create_test_config();
+        config.state_dir = Some(temp_dir.path().to_string_lossy().to_string());
+        CrashMarker::new(temp_dir.path()).enter("network").unwrap(); // Never cleared, as after a crash
+
+        // Act
+        let collector = TelemetryCollector::new(config);
+        collector.start_up().await.unwrap();
+        let status = collector.get_status().await;
+
+        // Assert
+        assert!(status.safe_mode);
+        assert_eq!(status.disabled_collectors, vec!["network".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_unscorable_event_policy() {
+        // Arrange
+        let mut config = create_test_config();
+        config.unscorable_policy = UnscorablePolicy::Drop;
+        let collector = TelemetryCollector::new(config).with_scorer(Box::new(|_: &TelemetryEvent| f64::NAN));
+        let mock_collector = MockTelemetryCollector::new();
+
+        // Act
+        collector.enqueue_scored(&mock_collector, create_test_process_event()).await;
+
+        // Assert
+        assert!(mock_collector.get_events().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_metadata_map_size_cap() {
+        // Arrange
+        let mut config = create_test_config();
+        config.max_metadata_keys = 5;
+        let collector = TelemetryCollector::new(config);
+        let mut event = create_test_process_event();
+        for i in 0..20 {
+            event.metadata.insert(format!("enrichment_{}", i), serde_json::json!(i));
+        }
+
+        // Act
+        collector.enforce_metadata_cap(&mut event);
+
+        // Assert
+        assert!(event.metadata.len() <= 6); // Cap plus the truncation flag
+        assert!(event.metadata.contains_key("risk_score"));
+        assert_eq!(event.metadata.get("metadata_truncated").unwrap(), true);
+    }
+
+    #[tokio::test]
+    async fn test_events_tagged_with_agent_build() {
+        // Arrange
+        let config = create_test_config();
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        // Act
+        collector.collect_process_telemetry(&mock_collector).await.unwrap();
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        assert!(!events.is_empty());
+        for event in events {
+            assert_eq!(event.metadata.get("agent_version").unwrap().as_str().unwrap(), env!("CARGO_PKG_VERSION"));
+            assert!(event.metadata.contains_key("build_id"));
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_process_exit_correlated_with_start() {
+        // Arrange
+        let config = create_test_config();
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        // Act
+        let mut child = std::process::Command::new("sleep").arg("0.2").spawn().unwrap();
+        let pid = child.id() as u64;
+        collector.collect_process_telemetry(&mock_collector).await.unwrap();
+        child.wait().unwrap();
+        collector.collect_process_telemetry(&mock_collector).await.unwrap();
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        let start = events.iter()
+            .find(|e| matches!(e.event_type, EventType::Process) && e.data.get("pid").and_then(|p| p.as_u64()) == Some(pid))
+            .expect("start event for child");
+        let exit = events.iter()
+            .find(|e| matches!(e.event_type, EventType::ProcessExit) && e.data.get("pid").and_then(|p| p.as_u64()) == Some(pid))
+            .expect("exit event for child");
+        assert_eq!(start.metadata.get("process_uid"), exit.metadata.get("process_uid"));
+        assert!(exit.data.contains_key("exit_code"));
+        assert!(exit.data.contains_key("duration_ms"));
+    }
+
+    #[tokio::test]
+    async fn test_event_schema_registry_rejects_missing_field() {
+        // Arrange
+        let mut registry = EventSchemaRegistry::new();
+        registry.register(EventType::Process, serde_json::json!({
+            "type": "object",
+            "properties": {
+                "data": { "type": "object", "required": ["process_name", "pid", "parent_pid"] }
+            }
+        })).unwrap();
+        let collector = TelemetryCollector::new(create_test_config()).with_schema_registry(registry);
+        let mock_collector = MockTelemetryCollector::new();
+
+        // Act
+        collector.enqueue_scored(&mock_collector, create_test_process_event()).await;
+
+        // Assert
+        assert!(mock_collector.get_events().await.is_empty());
+        assert_eq!(collector.get_status().await.schema_rejections, 1);
+    }
+
+    #[tokio::test]
+    async fn test_doh_resolver_risk_signal() {
+        // Arrange
+        let mut config = create_test_config();
+        config.doh_providers = vec!["1.1.1.1/32".to_string()];
+        let collector = TelemetryCollector::new(config);
+
+        let network_event = |ip: &str| {
+            let mut event = create_test_process_event();
+            event.event_type = EventType::Network;
+            event.data.insert("protocol".to_string(), serde_json::Value::String("tcp".to_string()));
+            event.data.insert("remote_ip".to_string(), serde_json::Value::String(ip.to_string()));
+            event.data.insert("remote_port".to_string(), serde_json::Value::Number(443.into()));
+            event
+        };
+        let mut doh = network_event("1.1.1.1");
+        let mut ordinary = network_event("93.184.216.34");
+
+        // Act
+        collector.calculate_risk_score(&mut doh).await;
+        collector.calculate_risk_score(&mut ordinary).await;
+
+        // Assert
+        assert!(doh.metadata["risk_breakdown"]["doh"].as_f64().unwrap() > 0.0);
+        assert!(ordinary.metadata["risk_breakdown"].get("doh").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tumbling_window_batches() {
+        // Arrange
+        let mut config = create_test_config();
+        config.batch_window = BatchWindow::Tumbling(5000);
+        let clock = MockClock::new(Utc::now());
+        let collector = TelemetryCollector::new(config).with_clock(clock.clone());
+        let mut batcher = collector.window_batcher();
+
+        // Act
+        for i in 0..3 {
+            let mut event = create_test_process_event();
+            event.id = format!("window-a-{}", i);
+            event.timestamp = clock.now();
+            batcher.push(event);
+            clock.advance(Duration::seconds(1));
+        }
+        clock.advance(Duration::seconds(3)); // Cross the 5s boundary
+        for i in 0..2 {
+            let mut event = create_test_process_event();
+            event.id = format!("window-b-{}", i);
+            event.timestamp = clock.now();
+            batcher.push(event);
+        }
+        clock.advance(Duration::seconds(5));
+        let batches = batcher.drain_ready();
+
+        // Assert
+        assert_eq!(batches.len(), 2);
+        assert_eq!(batches[0].len(), 3);
+        assert!(batches[0].iter().all(|e| e.id.starts_with("window-a-")));
+        assert_eq!(batches[1].len(), 2);
+        assert!(batches[1].iter().all(|e| e.id.starts_with("window-b-")));
+    }
+
+    #[tokio::test]
+    async fn test_process_watchlist_boost() {
+        // Arrange
+        let mut config = create_test_config();
+        config.process_watchlist.insert("mimikatz*".to_string(), 0.6);
+        let collector = TelemetryCollector::new(config);
+
+        let mut watched = create_test_process_event();
+        watched.data.insert("process_name".to_string(), serde_json::Value::String("MimiKatz.exe".to_string()));
+        let mut unrelated = create_test_process_event();
+
+        // Act
+        collector.calculate_risk_score(&mut watched).await;
+        collector.calculate_risk_score(&mut unrelated).await;
+
+        // Assert
+        assert_eq!(watched.metadata["risk_breakdown"]["watchlist"].as_f64().unwrap(), 0.6);
+        assert!(unrelated.metadata["risk_breakdown"].get("watchlist").is_none());
+        assert!(watched.metadata["risk_score"].as_f64().unwrap() <= 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_batch_assembly_yields_cooperatively() {
+        // Arrange
+        let mut config = create_test_config();
+        config.max_batch_size = 10000;
+        config.batch_assembly_yield_every = 1000;
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+        for i in 0..10000 {
+            let mut event = create_test_process_event();
+            event.id = format!("test-event-{}", i);
+            mock_collector.add_event(event).await;
+        }
+
+        // Act - count how often a sibling task gets to run during assembly
+        let ticks = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let ticker = tokio::spawn({
+            let ticks = Arc::clone(&ticks);
+            async move {
+                loop {
+                    ticks.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+        let batches = collector.create_batches(&mock_collector).await;
+        ticker.abort();
+
+        // Assert
+        assert_eq!(batches.iter().map(|b| b.len()).sum::<usize>(), 10000);
+        // Ten slices of 1000 events: the sibling runs at least between each pair
+        assert!(ticks.load(std::sync::atomic::Ordering::Relaxed) >= 10000 / 1000 - 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_container_attribution_from_cgroup() {
+        // Arrange
+        let cgroup = "0::/kubepods/burstable/pod6d2c1f0a-1b2c-4d5e-8f90-123456789abc/\
+                      3f4e5d6c7b8a99887766554433221100ffeeddccbbaa99887766554433221100\n";
+        let host_cgroup = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        let mut containerized = create_test_process_event();
+        let mut host = create_test_process_event();
+
+        // Act
+        apply_container_context(&mut containerized, cgroup);
+        apply_container_context(&mut host, host_cgroup);
+
+        // Assert
+        assert_eq!(
+            containerized.metadata.get("container_id").unwrap().as_str().unwrap(),
+            "3f4e5d6c7b8a99887766554433221100ffeeddccbbaa99887766554433221100"
+        );
+        assert_eq!(containerized.metadata.get("pod").unwrap().as_str().unwrap(), "6d2c1f0a-1b2c-4d5e-8f90-123456789abc");
+        assert!(!host.metadata.contains_key("container_id"));
+        assert!(!host.metadata.contains_key("pod"));
+    }
+
+    #[tokio::test]
+    async fn test_one_shot_mode() {
+        // Arrange
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.one_shot = true;
+        let collector = TelemetryCollector::new(config);
+
+        let _events_mock = mockito::mock("POST", "/api/v1/events")
+            .with_status(200)
+            .with_body(r#"{"status": "success"}"#)
+            .expect_at_least(1)
+            .create();
+
+        // Act
+        let outcome = tokio::time::timeout(std::time::Duration::from_secs(30), collector.run()).await;
+
+        // Assert
+        let outcome = outcome.expect("one-shot mode must not enter the collection loop").unwrap();
+        assert_eq!(outcome.exit_code(), 0);
+        assert_eq!(outcome.cycles, 1);
+        _events_mock.assert();
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_user_attribution_for_current_process() {
+        // Arrange
+        let collector = TelemetryCollector::new(create_test_config());
+        let mut event = create_test_process_event();
+        event.data.insert("pid".to_string(), serde_json::Value::Number(std::process::id().into()));
+
+        // Act
+        collector.enrich_user_context(&mut event).await;
+
+        // Assert
+        let uid = unsafe { libc::getuid() };
+        assert_eq!(event.metadata.get("uid").unwrap().as_u64().unwrap(), uid as u64);
+        assert!(event.metadata.contains_key("username"));
+        assert!(event.metadata.contains_key("session_id"));
+    }
+
+    #[tokio::test]
+    async fn test_network_connection_aggregation() {
+        // Arrange
+        let mut config = create_test_config();
+        config.connection_dedup_window_secs = 60;
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        for i in 0..50 {
+            let mut event = create_test_process_event();
+            event.id = format!("net-{}", i);
+            event.event_type = EventType::Network;
+            event.data.insert("protocol".to_string(), serde_json::Value::String("tcp".to_string()));
+            event.data.insert("remote_ip".to_string(), serde_json::Value::String("10.0.0.5".to_string()));
+            event.data.insert("remote_port".to_string(), serde_json::Value::Number(443.into()));
+            event.data.insert("bytes_sent".to_string(), serde_json::Value::Number(100.into()));
+            mock_collector.add_event(event).await;
+        }
+
+        // Act
+        collector.aggregate_connections(&mock_collector).await;
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].metadata.get("connection_count").unwrap().as_u64().unwrap(), 50);
+        assert_eq!(events[0].data.get("bytes_sent").unwrap().as_u64().unwrap(), 5000);
+    }
+
+    #[tokio::test]
+    async fn test_active_rules_hit_counter() {
+        // Arrange
+        let collector = TelemetryCollector::new(create_test_config());
+        collector.apply_rules(vec![
+            Rule::new("suspicious_process", true).matching_process("test.exe"),
+            Rule::new("file_access", false),
+        ]).await;
+
+        // Act
+        collector.evaluate_rules(&mut create_test_process_event()).await;
+        let rules = collector.active_rules().await;
+
+        // Assert
+        assert_eq!(rules.len(), 2);
+        let suspicious = rules.iter().find(|r| r.name == "suspicious_process").unwrap();
+        assert!(suspicious.enabled);
+        assert_eq!(suspicious.hits, 1);
+        assert!(!suspicious.matcher.is_empty());
+        let file_access = rules.iter().find(|r| r.name == "file_access").unwrap();
+        assert_eq!(file_access.hits, 0);
+    }
+
+    #[tokio::test]
+    async fn test_timestamp_granularity_seconds() {
+        // Arrange
+        let mut event = create_test_process_event();
+        event.timestamp = "2024-01-01T12:34:56.789123456Z".parse().unwrap();
+
+        // Act
+        let serialized = event.to_json_with_granularity(TimestampGranularity::Seconds).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&serialized).unwrap();
+
+        // Assert
+        let timestamp: chrono::DateTime<Utc> = value["timestamp"].as_str().unwrap().parse().unwrap();
+        assert_eq!(timestamp.timestamp_subsec_nanos(), 0);
+        assert_eq!(timestamp.timestamp(), event.timestamp.timestamp());
+    }
+
+    #[cfg(windows)]
+    #[tokio::test]
+    async fn test_zone_identifier_download_provenance() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let download = temp_dir.path().join("installer.exe");
+        tokio::fs::write(&download, b"MZ").await.unwrap();
+        let ads = format!("{}:Zone.Identifier", download.display());
+        tokio::fs::write(&ads, "[ZoneTransfer]\r\nZoneId=3\r\nReferrerUrl=https://referrer.example/\r\nHostUrl=https://downloads.example/installer.exe\r\n").await.unwrap();
+
+        let mut event = create_test_file_event();
+        event.data.insert("filename".to_string(), serde_json::Value::String(download.display().to_string()));
+
+        // Act
+        enrich_download_provenance(&mut event).await;
+
+        // Assert
+        assert_eq!(event.metadata.get("download_url").unwrap().as_str().unwrap(), "https://downloads.example/installer.exe");
+        assert_eq!(event.metadata.get("referrer").unwrap().as_str().unwrap(), "https://referrer.example/");
+    }
+
+    #[tokio::test]
+    async fn test_process_start_burst_merging() {
+        // Arrange
+        let mut config = create_test_config();
+        config.process_burst_window_ms = 500;
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        let parent = create_test_process_event();
+        for i in 0..20 {
+            let mut child = create_test_process_event();
+            child.id = format!("proc-child-{}", i);
+            child.timestamp = parent.timestamp + Duration::milliseconds(10 * i);
+            child.data.insert("pid".to_string(), serde_json::Value::Number((2000 + i).into()));
+            child.data.insert("parent_pid".to_string(), serde_json::Value::Number(1234.into()));
+            mock_collector.add_event(child).await;
+        }
+
+        // Act
+        collector.merge_process_bursts(&mock_collector).await;
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data.get("parent_pid").unwrap(), 1234);
+        assert_eq!(events[0].data.get("children").unwrap().as_array().unwrap().len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_event_store_query() {
+        // Arrange
+        let store = MemoryEventStore::new();
+        let mut high_risk_process = create_test_process_event();
+        high_risk_process.id = "high-risk-process".to_string();
+        high_risk_process.metadata.insert("risk_score".to_string(), serde_json::json!(0.9));
+        store.add(high_risk_process).await;
+        store.add(create_test_process_event()).await; // risk 0.3
+        let mut high_risk_file = create_test_file_event();
+        high_risk_file.metadata.insert("risk_score".to_string(), serde_json::json!(0.9));
+        store.add(high_risk_file).await;
+
+        // Act
+        let filter = EventQuery {
+            event_type: Some(EventType::Process),
+            since: Some(Utc::now() - Duration::minutes(5)),
+            until: None,
+            min_risk: Some(0.5),
+        };
+        let results = store.query(&filter, 10).await.unwrap();
+
+        // Assert
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "high-risk-process");
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_rotation_compression_and_cap() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let logger = AuditLogger::builder(temp_dir.path())
+            .segment_max_bytes(256)
+            .compress_rotated(true)
+            .max_total_bytes(1024)
+            .build()
+            .unwrap();
+
+        // Act
+        for i in 0..200 {
+            logger.record("config_reloaded", &format!("reload {}", i)).await.unwrap();
+        }
+
+        // Assert
+        let segments = logger.rotated_segments().unwrap();
+        assert!(!segments.is_empty());
+        assert!(segments.iter().all(|p| p.extension().map_or(false, |e| e == "gz")));
+        assert!(logger.total_disk_usage().unwrap() <= 1024);
+        assert!(logger.removed_segments() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_file_hash_concurrency_limit() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = create_test_config();
+        config.max_parallel_hashes = 2;
+        config.enrichment_parallelism = 8;
+        let collector = TelemetryCollector::new(config);
+
+        let mut events = Vec::new();
+        for i in 0..16 {
+            let path = temp_dir.path().join(format!("hash-target-{}.bin", i));
+            tokio::fs::write(&path, vec![0u8; 1024 * 1024]).await.unwrap();
+            let mut event = create_test_file_event();
+            event.data.insert("filename".to_string(), serde_json::Value::String(path.display().to_string()));
+            events.push(event);
+        }
+
+        // Act
+        let enriched = collector.enrich_file_hashes(events).await;
+
+        // Assert
+        assert_eq!(enriched.len(), 16);
+        assert!(enriched.iter().all(|e| e.data.contains_key("sha256")));
+        assert!(collector.peak_concurrent_hashes() <= 2);
+    }
+
+    #[tokio::test]
+    async fn test_critical_batch_retry_state_survives_restart() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = create_test_config();
+        let store = FileEventStore::open(temp_dir.path(), &config).await.unwrap();
+        let batch_id = store.enqueue_critical(vec![create_test_process_event()]).await.unwrap();
+        store.record_retry_attempt(&batch_id).await.unwrap();
+        store.record_retry_attempt(&batch_id).await.unwrap();
+        drop(store); // Simulated restart
+
+        // Act
+        let store = FileEventStore::open(temp_dir.path(), &config).await.unwrap();
+        let state = store.retry_state(&batch_id).await.unwrap();
+
+        // Assert
+        assert_eq!(state.attempts, 2);
+        assert_eq!(state.next_attempt_number(), 3);
+        assert!(state.next_attempt_at.is_some());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_authorized_keys_change_flagged() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let ssh_dir = temp_dir.path().join("alice/.ssh");
+        tokio::fs::create_dir_all(&ssh_dir).await.unwrap();
+        let authorized_keys = ssh_dir.join("authorized_keys");
+        tokio::fs::write(&authorized_keys, "ssh-ed25519 AAAAC3Nza...existing alice@laptop\n").await.unwrap();
+
+        let mut config = create_test_config();
+        config.ssh_key_home_roots = vec![temp_dir.path().to_string_lossy().to_string()];
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+        collector.collect_ssh_key_telemetry(&mock_collector).await.unwrap();
+
+        // Act
+        let mut keys = tokio::fs::read_to_string(&authorized_keys).await.unwrap();
+        keys.push_str("ssh-ed25519 AAAAC3Nza...attacker evil@host\n");
+        tokio::fs::write(&authorized_keys, keys).await.unwrap();
+        collector.collect_ssh_key_telemetry(&mock_collector).await.unwrap();
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        let flagged = events.iter()
+            .find(|e| e.metadata.get("persistence_technique") == Some(&serde_json::Value::String("ssh_key".to_string())))
+            .expect("authorized_keys change should be flagged");
+        assert!(matches!(flagged.event_type, EventType::File));
+        assert!(flagged.metadata.get("risk_score").unwrap().as_f64().unwrap() >= 0.8);
+        assert!(flagged.data.get("diff_summary").unwrap().as_str().unwrap().contains("+1"));
+    }
+
+    #[tokio::test]
+    async fn test_stale_enrichment_discarded_on_pid_reuse() {
+        // Arrange
+        let collector = TelemetryCollector::new(create_test_config());
+        let mut event = create_test_process_event();
+        event.metadata.insert("process_start_token".to_string(), serde_json::json!(1000));
+
+        // Enrichment result read from a new process that reused pid 1234
+        let lookup = ProcessEnrichment {
+            pid: 1234,
+            start_token: 2000,
+            fields: [("parent_name".to_string(), serde_json::json!("sshd"))].into_iter().collect(),
+        };
+
+        // Act
+        let applied = collector.apply_enrichment(&mut event, lookup);
+
+        // Assert
+        assert!(!applied);
+        assert!(!event.metadata.contains_key("parent_name"));
+    }
+
+    #[tokio::test]
+    async fn test_file_modify_storm_coalescing() {
+        // Arrange
+        let mut config = create_test_config();
+        config.file_coalesce_window_ms = 1000;
+        let collector = TelemetryCollector::new(config);
+        let mock_collector = MockTelemetryCollector::new();
+
+        let start = Utc::now();
+        for i in 0..30 {
+            let mut event = create_test_file_event();
+            event.id = format!("file-modify-{}", i);
+            event.timestamp = start + Duration::milliseconds(10 * i);
+            event.data.insert("operation".to_string(), serde_json::Value::String("modify".to_string()));
+            event.data.insert("size".to_string(), serde_json::Value::Number(100.into()));
+            mock_collector.add_event(event).await;
+        }
+        let mut delete = create_test_file_event();
+        delete.id = "file-delete".to_string();
+        delete.timestamp = start + Duration::milliseconds(400);
+        delete.data.insert("operation".to_string(), serde_json::Value::String("delete".to_string()));
+        mock_collector.add_event(delete).await;
+
+        // Act
+        collector.coalesce_file_events(&mock_collector).await;
+        let events = mock_collector.get_events().await;
+
+        // Assert
+        assert_eq!(events.len(), 2);
+        let modify = events.iter().find(|e| e.data.get("operation").and_then(|v| v.as_str()) == Some("modify")).unwrap();
+        assert_eq!(modify.metadata.get("coalesced_count").unwrap().as_u64().unwrap(), 30);
+        assert_eq!(modify.data.get("bytes_written").unwrap().as_u64().unwrap(), 3000);
+        assert!(modify.metadata.contains_key("first_seen"));
+        assert!(modify.metadata.contains_key("last_seen"));
+        assert!(events.iter().any(|e| e.id == "file-delete"));
+    }
+
+    #[tokio::test]
+    async fn test_startup_splay_delays_registration() {
+        // Arrange
+        let mut config = create_test_config();
+        config.server_url = mockito::server_url();
+        config.startup_splay_secs = 30;
+        let (sleeper, mut requested) = MockSleeper::new();
+        let collector = TelemetryCollector::new(config).with_sleeper(sleeper.clone());
+
+        let _register_mock = mockito::mock("POST", "/api/v1/agents/register")
+            .with_status(201)
+            .with_body(r#"{"status": "registered", "agent_id": "test-agent-123"}"#)
+            .expect(1)
+            .create();
+
+        let delay = collector.planned_startup_splay();
+        assert!(delay <= std::time::Duration::from_secs(30));
+
+        // Act
+        let startup = tokio::spawn({
+            let collector = collector.clone();
+            async move { collector.start_up().await }
+        });
+
+        // The splay is requested first and nothing is registered while it is held
+        let slept = tokio::time::timeout(std::time::Duration::from_secs(5), requested.recv())
+            .await
+            .expect("startup never requested the splay sleep")
+            .unwrap();
+        assert_eq!(slept, delay);
+        assert!(!_register_mock.matched());
+
+        sleeper.release();
+        tokio::time::timeout(std::time::Duration::from_secs(5), startup)
+            .await
+            .expect("startup did not finish after the splay")
+            .unwrap()
+            .unwrap();
+
+        // Assert
+        _register_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_default_severity_mapping() {
+        // Arrange
+        let mapping = SeverityMapping::default();
+        let mut critical = create_test_process_event();
+        critical.metadata.insert("risk_score".to_string(), serde_json::json!(0.95));
+        let mut low = create_test_file_event();
+        low.metadata.insert("risk_score".to_string(), serde_json::json!(0.1));
+
+        // Act
+        mapping.apply(&mut critical);
+        mapping.apply(&mut low);
+
+        // Assert
+        assert_eq!(critical.metadata.get("severity").unwrap().as_str().unwrap(), "critical");
+        assert_eq!(low.metadata.get("severity").unwrap().as_str().unwrap(), "low");
+    }
+
+    #[tokio::test]
+    async fn test_future_timestamped_event_held_until_due() {
+        // Arrange
+        let mut config = create_test_config();
+        config.future_timestamp_policy = FutureTimestampPolicy::Buffer;
+        let clock = MockClock::new(Utc::now());
+        let collector = TelemetryCollector::new(config).with_clock(clock.clone());
+        let mut guard = collector.ordering_guard();
+
+        let mut future_event = create_test_process_event();
+        future_event.timestamp = clock.now() + Duration::seconds(5);
+
+        // Act
+        guard.push(future_event);
+        let released_early = guard.release_due();
+        clock.advance(Duration::seconds(6));
+        let released_later = guard.release_due();
+
+        // Assert
+        assert!(released_early.is_empty());
+        assert_eq!(released_later.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_sequence_numbers_persist_across_restart() {
+        // Arrange
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut config = create_test_config();
+        config.state_dir = Some(temp_dir.path().to_string_lossy().to_string());
+        config.include_sequence_numbers = true;
+
+        // Act
+        let collector = TelemetryCollector::new(config.clone());
+        let mut first = create_test_process_event();
+        let mut second = create_test_process_event();
+        collector.stamp_sequence(&mut first).await;
+        collector.stamp_sequence(&mut second).await;
+        drop(collector); // Simulated restart
+
+        let collector = TelemetryCollector::new(config);
+        let mut after_restart = create_test_process_event();
+        collector.stamp_sequence(&mut after_restart).await;
+
+        // Assert
+        let seq = |e: &TelemetryEvent| e.metadata.get("seq").unwrap().as_u64().unwrap();
+        assert!(seq(&first) < seq(&second));
+        assert!(seq(&second) < seq(&after_restart));
+    }
+
     // Benchmark tests
     #[bench]
     fn bench_event_creation(b: &mut test::Bencher) {